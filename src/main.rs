@@ -1,5 +1,6 @@
-use std::time::Duration;
+use std::collections::VecDeque;
 
+use bevy::asset::LoadState;
 use bevy::log::LogSettings;
 use bevy::prelude::*;
 use rand::Rng;
@@ -8,11 +9,19 @@ const ARENA_WIDTH: u32 = 15;
 const ARENA_HEIGHT: u32 = 15;
 const FOOD_SPAWN_PERIOD_MS: u64 = 1500;
 const SNAKE_MOVEMENT_PERIOD_MS: u64 = 250;
+const INPUT_QUEUE_CAPACITY: usize = 2;
+const SNAKE_SPEED_DECAY: f32 = 0.95;
+const MIN_SNAKE_MOVEMENT_PERIOD_MS: f32 = 80.0;
+const MAX_FOOD: usize = 3;
+const FOOD_PLACEMENT_ATTEMPTS: u32 = 3;
+const ARENA_NEARLY_FULL_THRESHOLD: f32 = 0.6;
 
 const SNAKE_HEAD_COLOR: Color = Color::rgb(0.7, 0.7, 0.7);
 const SNAKE_SEGMENT_COLOR: Color = Color::rgb(0.3, 0.3, 0.3);
 const FOOD_COLOR: Color = Color::rgb(1.0, 0.0, 1.0);
 
+const HIGH_SCORE_FILE: &str = "high_score.txt";
+
 #[derive(Eq, PartialEq, Hash, Clone, Debug)]
 enum AppState {
     Starting,
@@ -43,7 +52,7 @@ impl Direction {
 #[derive(Component)]
 struct SnakeHead {
     direction: Direction,
-    input_direction: Option<Direction>,
+    input_queue: VecDeque<Direction>,
 }
 
 #[derive(Component)]
@@ -79,16 +88,149 @@ impl Size {
 #[derive(Component)]
 struct Food;
 
-struct FoodSpawnerConfig {
-    timer: Timer,
+struct Cadence {
+    elapsed_ms: f32,
+}
+
+impl Cadence {
+    fn tick(&mut self, delta_ms: f32, period_ms: f32) -> bool {
+        self.elapsed_ms += delta_ms;
+        if self.elapsed_ms < period_ms {
+            return false;
+        }
+        self.elapsed_ms -= period_ms;
+        true
+    }
+}
+
+struct MovementSpeed {
+    period_ms: f32,
+    cadence: Cadence,
 }
 
-struct SnakeMovementConfig {
-    timer: Timer,
+struct FoodSpawnClock {
+    cadence: Cadence,
+}
+
+struct GameAssets {
+    snake_head: Handle<Image>,
+    snake_segment: Handle<Image>,
+    food: Handle<Image>,
+}
+
+struct GrowthEvent(Position);
+
+struct GameOverEvent;
+
+#[derive(Default)]
+struct Score(u32);
+
+struct HighScore(u32);
+
+#[derive(Component)]
+struct ScoreText;
+
+#[derive(Component)]
+struct GameOverText;
+
+#[derive(SystemLabel, Debug, Clone, Eq, PartialEq, Hash)]
+enum SnakeMovement {
+    Input,
+    Movement,
+    Eating,
+    Growth,
 }
 
 fn setup_game(mut commands: Commands) {
     commands.spawn_bundle(Camera2dBundle::default());
+    commands.spawn_bundle(UiCameraBundle::default());
+}
+
+fn load_high_score() -> u32 {
+    std::fs::read_to_string(HIGH_SCORE_FILE)
+        .ok()
+        .and_then(|contents| contents.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+fn save_high_score(score: u32) {
+    let _ = std::fs::write(HIGH_SCORE_FILE, score.to_string());
+}
+
+fn load_score(mut commands: Commands) {
+    commands.insert_resource(Score::default());
+    commands.insert_resource(HighScore(load_high_score()));
+}
+
+fn spawn_score_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(8.0),
+                    left: Val::Px(8.0),
+                    ..default()
+                },
+                ..default()
+            },
+            text: Text::with_section(
+                "Score: 0",
+                TextStyle {
+                    font: asset_server.load("fonts/DejaVuSans-Bold.ttf"),
+                    font_size: 32.0,
+                    color: Color::WHITE,
+                },
+                Default::default(),
+            ),
+            ..default()
+        })
+        .insert(ScoreText);
+}
+
+fn update_score_text(score: Res<Score>, mut q: Query<&mut Text, With<ScoreText>>) {
+    for mut text in q.iter_mut() {
+        text.sections[0].value = format!("Score: {}", score.0);
+    }
+}
+
+fn show_game_over(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    score: Res<Score>,
+    mut high_score: ResMut<HighScore>,
+) {
+    if score.0 > high_score.0 {
+        high_score.0 = score.0;
+    }
+    save_high_score(high_score.0);
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(200.0),
+                    left: Val::Px(80.0),
+                    ..default()
+                },
+                ..default()
+            },
+            text: Text::with_section(
+                format!(
+                    "Game Over — Score: {} — Best: {}",
+                    score.0, high_score.0
+                ),
+                TextStyle {
+                    font: asset_server.load("fonts/DejaVuSans-Bold.ttf"),
+                    font_size: 28.0,
+                    color: Color::WHITE,
+                },
+                Default::default(),
+            ),
+            ..default()
+        })
+        .insert(GameOverText);
 }
 
 fn size_scaling(windows: Res<Windows>, mut q: Query<(&Size, &mut Transform)>) {
@@ -127,22 +269,31 @@ fn snake_head_movement_input(
     mut q: Query<&mut SnakeHead>,
 ) {
     for mut head in q.iter_mut() {
-        let dir: Option<Direction> = if keyboard_input.pressed(KeyCode::Left) {
+        let dir: Option<Direction> = if keyboard_input.just_pressed(KeyCode::Left) {
             Some(Direction::Left)
-        } else if keyboard_input.pressed(KeyCode::Right) {
+        } else if keyboard_input.just_pressed(KeyCode::Right) {
             Some(Direction::Right)
-        } else if keyboard_input.pressed(KeyCode::Up) {
+        } else if keyboard_input.just_pressed(KeyCode::Up) {
             Some(Direction::Up)
-        } else if keyboard_input.pressed(KeyCode::Down) {
+        } else if keyboard_input.just_pressed(KeyCode::Down) {
             Some(Direction::Down)
         } else {
             None
         };
 
-        if dir.is_some() {
-            if dir.unwrap() != head.direction.opposite() {
-                head.input_direction = dir;
-            }
+        let dir = match dir {
+            Some(dir) => dir,
+            None => continue,
+        };
+        if head.input_queue.len() >= INPUT_QUEUE_CAPACITY {
+            continue;
+        }
+        let last_queued = *head
+            .input_queue
+            .back()
+            .unwrap_or(&head.direction);
+        if dir != last_queued.opposite() {
+            head.input_queue.push_back(dir);
         }
     }
 }
@@ -211,22 +362,22 @@ fn snake_movement(
         (With<SnakeSegment>, Without<SnakeHead>),
     >,
     time: Res<Time>,
-    mut config: ResMut<SnakeMovementConfig>,
-    mut app_state: ResMut<State<AppState>>,
+    mut speed: ResMut<MovementSpeed>,
+    mut game_over_writer: EventWriter<GameOverEvent>,
 ) {
-    config.timer.tick(time.delta());
-    if !config.timer.finished() {
+    let period_ms = speed.period_ms;
+    if !speed.cadence.tick(time.delta_seconds() * 1000.0, period_ms) {
         return;
     }
 
     for (mut head_pos, mut head, body, mut last_tail_pos) in heads.iter_mut() {
-        if head.input_direction.is_some() {
-            head.direction = head.input_direction.unwrap();
+        if let Some(next_direction) = head.input_queue.pop_front() {
+            head.direction = next_direction;
         }
         let next_head_pos =
             get_next_head_pos(*head_pos, head.direction, &segments);
         if next_head_pos.is_none() {
-            app_state.set(AppState::Ended).unwrap();
+            game_over_writer.send(GameOverEvent);
             return;
         }
 
@@ -242,28 +393,56 @@ fn snake_movement(
     }
 }
 
-fn snake_eating_and_growth(
+fn snake_eating(
     mut commands: Commands,
     food_positions: Query<(&Position, Entity), With<Food>>,
-    mut head_positions: Query<
-        (&Position, &LastTailPos, &mut SnakeBody),
-        With<SnakeHead>,
-    >,
+    head_positions: Query<(&Position, &LastTailPos), With<SnakeHead>>,
+    mut growth_writer: EventWriter<GrowthEvent>,
 ) {
-    for (head_pos, last_tail_pos, mut body) in head_positions.iter_mut() {
+    for (head_pos, last_tail_pos) in head_positions.iter() {
         for (food_pos, food) in food_positions.iter() {
             if head_pos == food_pos {
                 commands.entity(food).despawn();
-                spawn_segment(&mut commands, last_tail_pos.0, &mut *body);
+                growth_writer.send(GrowthEvent(last_tail_pos.0));
             }
         }
     }
 }
 
+fn snake_growth(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    asset_server: Res<AssetServer>,
+    mut heads: Query<&mut SnakeBody, With<SnakeHead>>,
+    mut growth_reader: EventReader<GrowthEvent>,
+    mut speed: ResMut<MovementSpeed>,
+    mut score: ResMut<Score>,
+) {
+    for event in growth_reader.iter() {
+        for mut body in heads.iter_mut() {
+            spawn_segment(&mut commands, &assets, &asset_server, event.0, &mut *body);
+        }
+        speed.period_ms =
+            (speed.period_ms * SNAKE_SPEED_DECAY).max(MIN_SNAKE_MOVEMENT_PERIOD_MS);
+        score.0 += 1;
+    }
+}
+
+fn game_over(
+    mut game_over_reader: EventReader<GameOverEvent>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    if game_over_reader.iter().next().is_some() {
+        app_state.set(AppState::Ended).unwrap();
+    }
+}
+
 fn game_reset(
     mut commands: Commands,
     foods: Query<Entity, With<Food>>,
     segments: Query<Entity, With<SnakeSegment>>,
+    game_over_texts: Query<Entity, With<GameOverText>>,
+    mut score: ResMut<Score>,
 ) {
     for food in foods.iter() {
         commands.entity(food).despawn();
@@ -271,17 +450,58 @@ fn game_reset(
     for segment in segments.iter() {
         commands.entity(segment).despawn();
     }
+    for text in game_over_texts.iter() {
+        commands.entity(text).despawn();
+    }
+    score.0 = 0;
 }
 
-fn spawn_segment(commands: &mut Commands, pos: Position, body: &mut SnakeBody) {
-    let segment = commands
-        .spawn_bundle(SpriteBundle {
+fn textured_sprite_bundle(
+    asset_server: &AssetServer,
+    texture: &Handle<Image>,
+    fallback_color: Color,
+) -> SpriteBundle {
+    if asset_server.get_load_state(texture) == LoadState::Failed {
+        SpriteBundle {
             sprite: Sprite {
-                color: SNAKE_SEGMENT_COLOR,
+                color: fallback_color,
                 ..default()
             },
             ..default()
-        })
+        }
+    } else {
+        SpriteBundle {
+            texture: texture.clone(),
+            sprite: Sprite {
+                custom_size: Some(Vec2::ONE),
+                ..default()
+            },
+            ..default()
+        }
+    }
+}
+
+fn load_assets(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.insert_resource(GameAssets {
+        snake_head: asset_server.load("textures/snake_head.png"),
+        snake_segment: asset_server.load("textures/snake_segment.png"),
+        food: asset_server.load("textures/food.png"),
+    });
+}
+
+fn spawn_segment(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    asset_server: &AssetServer,
+    pos: Position,
+    body: &mut SnakeBody,
+) {
+    let segment = commands
+        .spawn_bundle(textured_sprite_bundle(
+            asset_server,
+            &assets.snake_segment,
+            SNAKE_SEGMENT_COLOR,
+        ))
         .insert(SnakeSegment)
         .insert(pos)
         .insert(Size::square(0.75))
@@ -289,18 +509,21 @@ fn spawn_segment(commands: &mut Commands, pos: Position, body: &mut SnakeBody) {
     body.0.push(segment);
 }
 
-fn spawn_snake(mut commands: Commands, mut app_state: ResMut<State<AppState>>) {
+fn spawn_snake(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    asset_server: Res<AssetServer>,
+    mut app_state: ResMut<State<AppState>>,
+) {
     let head = commands
-        .spawn_bundle(SpriteBundle {
-            sprite: Sprite {
-                color: SNAKE_HEAD_COLOR,
-                ..default()
-            },
-            ..default()
-        })
+        .spawn_bundle(textured_sprite_bundle(
+            &asset_server,
+            &assets.snake_head,
+            SNAKE_HEAD_COLOR,
+        ))
         .insert(SnakeHead {
             direction: Direction::Up,
-            input_direction: None,
+            input_queue: VecDeque::with_capacity(INPUT_QUEUE_CAPACITY),
         })
         .insert(SnakeSegment)
         .insert(Position { x: 3, y: 3 })
@@ -309,64 +532,95 @@ fn spawn_snake(mut commands: Commands, mut app_state: ResMut<State<AppState>>) {
 
     let tail_pos = Position { x: 3, y: 2 };
     let mut body = SnakeBody(Vec::default());
-    spawn_segment(&mut commands, tail_pos, &mut body);
+    spawn_segment(&mut commands, &assets, &asset_server, tail_pos, &mut body);
 
     commands
         .entity(head)
         .insert(body)
         .insert(LastTailPos(tail_pos));
 
-    commands.insert_resource(SnakeMovementConfig {
-        timer: Timer::new(
-            Duration::from_millis(SNAKE_MOVEMENT_PERIOD_MS),
-            true,
-        ),
+    commands.insert_resource(MovementSpeed {
+        period_ms: SNAKE_MOVEMENT_PERIOD_MS as f32,
+        cadence: Cadence { elapsed_ms: 0.0 },
+    });
+    commands.insert_resource(FoodSpawnClock {
+        cadence: Cadence { elapsed_ms: 0.0 },
     });
     app_state.set(AppState::Running).unwrap();
 }
 
-fn start_food_spawner(mut commands: Commands) {
-    commands.insert_resource(FoodSpawnerConfig {
-        timer: Timer::new(Duration::from_millis(FOOD_SPAWN_PERIOD_MS), true),
-    })
+fn is_vacant(
+    pos: Position,
+    segments: &Query<&Position, With<SnakeSegment>>,
+    foods: &Query<&Position, With<Food>>,
+) -> bool {
+    segments.iter().all(|segment_pos| *segment_pos != pos)
+        && foods.iter().all(|food_pos| *food_pos != pos)
 }
 
 fn food_spawner(
     mut commands: Commands,
+    assets: Res<GameAssets>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    mut clock: ResMut<FoodSpawnClock>,
     segments: Query<&Position, With<SnakeSegment>>,
     foods: Query<&Position, With<Food>>,
-    time: Res<Time>,
-    mut config: ResMut<FoodSpawnerConfig>,
 ) {
-    config.timer.tick(time.delta());
+    if !clock
+        .cadence
+        .tick(time.delta_seconds() * 1000.0, FOOD_SPAWN_PERIOD_MS as f32)
+    {
+        return;
+    }
 
-    if config.timer.finished() && foods.is_empty() {
-        let mut rng = rand::thread_rng();
-        let pos = Position {
+    let food_count = foods.iter().count();
+    if food_count >= MAX_FOOD {
+        return;
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut pos = None;
+    for _ in 0..FOOD_PLACEMENT_ATTEMPTS {
+        let candidate = Position {
             x: rng.gen_range(0..ARENA_WIDTH as i32),
             y: rng.gen_range(0..ARENA_HEIGHT as i32),
         };
-
-        let mut vacant = true;
-        for segment_pos in segments.iter() {
-            if pos == *segment_pos {
-                vacant = false;
-            }
+        if is_vacant(candidate, &segments, &foods) {
+            pos = Some(candidate);
+            break;
         }
-        if vacant {
-            commands
-                .spawn_bundle(SpriteBundle {
-                    sprite: Sprite {
-                        color: FOOD_COLOR,
-                        ..default()
-                    },
-                    ..default()
+    }
+
+    if pos.is_none() {
+        let occupied = segments.iter().count() + food_count;
+        let arena_size = (ARENA_WIDTH * ARENA_HEIGHT) as usize;
+        let nearly_full =
+            occupied as f32 / arena_size as f32 >= ARENA_NEARLY_FULL_THRESHOLD;
+        if nearly_full {
+            let vacant_cells: Vec<Position> = (0..ARENA_WIDTH as i32)
+                .flat_map(|x| {
+                    (0..ARENA_HEIGHT as i32).map(move |y| Position { x, y })
                 })
-                .insert(Food)
-                .insert(pos)
-                .insert(Size::square(0.8));
+                .filter(|candidate| is_vacant(*candidate, &segments, &foods))
+                .collect();
+            if !vacant_cells.is_empty() {
+                pos = Some(vacant_cells[rng.gen_range(0..vacant_cells.len())]);
+            }
         }
     }
+
+    if let Some(pos) = pos {
+        commands
+            .spawn_bundle(textured_sprite_bundle(
+                &asset_server,
+                &assets.food,
+                FOOD_COLOR,
+            ))
+            .insert(Food)
+            .insert(pos)
+            .insert(Size::square(0.8));
+    }
 }
 
 fn main() {
@@ -382,24 +636,42 @@ fn main() {
             ..default()
         })
         .insert_resource(ClearColor(Color::rgb(0.04, 0.04, 0.04)))
+        .add_event::<GrowthEvent>()
+        .add_event::<GameOverEvent>()
         .add_startup_system(setup_game)
+        .add_startup_system(load_assets)
+        .add_startup_system(load_score)
+        .add_startup_system(spawn_score_ui)
         .add_system(game_control_input)
+        .add_system(update_score_text)
         .add_system_set(
-            SystemSet::on_update(AppState::Starting)
-                .with_system(spawn_snake)
-                .with_system(start_food_spawner),
+            SystemSet::on_update(AppState::Starting).with_system(spawn_snake),
         )
         .add_system_set(
-            SystemSet::on_update(AppState::Running)
-                .with_system(snake_movement)
-                .with_system(snake_eating_and_growth.after(snake_movement)),
+            SystemSet::on_enter(AppState::Ended).with_system(show_game_over),
         )
         .add_system_set(
             SystemSet::on_update(AppState::Running)
-                .with_system(snake_head_movement_input.before(snake_movement)),
-        )
-        .add_system_set(
-            SystemSet::on_update(AppState::Running).with_system(food_spawner),
+                .with_system(
+                    snake_head_movement_input.label(SnakeMovement::Input),
+                )
+                .with_system(
+                    snake_movement
+                        .label(SnakeMovement::Movement)
+                        .after(SnakeMovement::Input),
+                )
+                .with_system(
+                    snake_eating
+                        .label(SnakeMovement::Eating)
+                        .after(SnakeMovement::Movement),
+                )
+                .with_system(
+                    snake_growth
+                        .label(SnakeMovement::Growth)
+                        .after(SnakeMovement::Eating),
+                )
+                .with_system(game_over.after(SnakeMovement::Movement))
+                .with_system(food_spawner),
         )
         .add_system_set(
             SystemSet::on_exit(AppState::Ended).with_system(game_reset),